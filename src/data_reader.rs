@@ -1,7 +1,8 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fs::File,
-    io::{self, BufRead, BufReader},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpStream, UdpSocket},
     path::Path,
     sync::mpsc::{self, TryRecvError},
     thread,
@@ -16,6 +17,105 @@ enum Commands {
     Stop,
     StopLogger,
     StartLogging(Box<Path>),
+    ReadParam { board_id: u8, param_id: u8 },
+    WriteParam { board_id: u8, param_id: u8, value: i16 },
+}
+
+/// where frames are read from
+#[derive(Debug, Clone)]
+pub enum Endpoint {
+    Serial { path: String, baud: u32 },
+    /// connect to a `host:port` TCP server
+    Tcp(String),
+    /// listen for datagrams on a local `host:port`
+    Udp(String),
+}
+
+/// transport-agnostic byte source feeding a `FrameReader`
+#[derive(Debug)]
+enum Source {
+    Serial(Box<dyn SerialPort>),
+    Tcp(TcpStream),
+    /// socket plus the most recently seen peer, so replies can be `send_to`'d back to it
+    Udp(UdpSocket, Option<SocketAddr>),
+}
+
+impl Source {
+    /// how long a read may block before `reader_main` gets a chance to poll `command_rx` / time
+    /// out a pending parameter request
+    const READ_TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn open(endpoint: &Endpoint) -> io::Result<Self> {
+        match endpoint {
+            Endpoint::Serial { path, baud } => {
+                let port = serialport::new(path.as_str(), *baud)
+                    .timeout(Self::READ_TIMEOUT)
+                    .open()?;
+                Ok(Source::Serial(port))
+            }
+            Endpoint::Tcp(addr) => {
+                let stream = TcpStream::connect(addr)?;
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(Self::READ_TIMEOUT))?;
+                Ok(Source::Tcp(stream))
+            }
+            Endpoint::Udp(addr) => {
+                let socket = UdpSocket::bind(addr)?;
+                socket.set_read_timeout(Some(Self::READ_TIMEOUT))?;
+                Ok(Source::Udp(socket, None))
+            }
+        }
+    }
+}
+
+impl Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Source::Serial(s) => s.read(buf),
+            Source::Tcp(s) => s.read(buf),
+            // one datagram is one frame payload, so a single `recv_from` is fed straight into
+            // framing; remember the sender so replies can be routed back to it
+            Source::Udp(s, peer) => {
+                let (n, from) = s.recv_from(buf)?;
+                *peer = Some(from);
+                Ok(n)
+            }
+        }
+    }
+}
+
+impl Write for Source {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Source::Serial(s) => s.write(buf),
+            Source::Tcp(s) => s.write(buf),
+            Source::Udp(s, peer) => {
+                let peer = peer.ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotConnected, "no UDP peer seen yet")
+                })?;
+                s.send_to(buf, peer)
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Source::Serial(s) => s.flush(),
+            Source::Tcp(s) => s.flush(),
+            Source::Udp(..) => Ok(()),
+        }
+    }
+}
+
+/// how frames are encoded on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameMode {
+    /// one `sensor board value timestamp` line per frame, see `TryFrom<&str> for Inbound`
+    #[default]
+    Ascii,
+    /// COBS-framed (`0x00` delimited) tag byte plus packed little-endian payload, see
+    /// `TryFrom<&[u8]> for Inbound`
+    Cobs,
 }
 
 #[derive(Debug)]
@@ -26,12 +126,25 @@ struct Frame {
     timestamp: u32,
 }
 
+/// anything that can arrive over the wire: a sensor sample, or a reply to a parameter request
+#[derive(Debug)]
+enum Inbound {
+    Data(Frame),
+    Param { board_id: u8, param_id: u8, value: i16 },
+}
+
 #[derive(Debug, Default)]
 pub struct Reader {
     comm: Option<ReaderComm>,
     status: ReaderStatus,
     /// {(board_id, sensor_id) ->  data}
-    pub data: HashMap<(u8, u8), Vec<[f64; 2]>>,
+    pub data: HashMap<(u8, u8), VecDeque<[f64; 2]>>,
+    /// latest known value per (board_id, param_id), from `read_parameter`/`write_parameter` replies
+    pub params: HashMap<(u8, u8), i16>,
+    /// drop the oldest samples of a series once it holds more than this many points
+    pub max_points: Option<usize>,
+    /// drop samples older than this many timestamp units relative to the newest sample
+    pub max_age: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -40,6 +153,8 @@ enum ReaderStatus {
     Running,
     Logging,
     Stopped(Option<String>),
+    ParamValue { board_id: u8, param_id: u8, value: i16 },
+    CmdTimeout { board_id: u8, param_id: u8 },
 }
 
 #[derive(Debug)]
@@ -51,8 +166,12 @@ struct ReaderComm {
 
 #[derive(Debug)]
 struct FrameReader {
-    port: BufReader<Box<dyn SerialPort>>,
-    buf: String,
+    port: BufReader<Source>,
+    mode: FrameMode,
+    /// scratch buffer for the ASCII line protocol
+    ascii_buf: String,
+    /// scratch buffer for one COBS-delimited packet
+    cobs_buf: Vec<u8>,
 }
 
 impl Default for ReaderStatus {
@@ -70,13 +189,28 @@ impl Reader {
 
         let mut ret = None;
 
+        let max_points = self.max_points;
+        let max_age = self.max_age;
         let e = loop {
             match r.frame_rx.try_recv() {
-                Ok(f) => self
-                    .data
-                    .entry((f.board_id, f.sensor_id))
-                    .or_default()
-                    .push([f.timestamp as f64, f.value as f64]),
+                Ok(f) => {
+                    let series = self.data.entry((f.board_id, f.sensor_id)).or_default();
+                    series.push_back([f.timestamp as f64, f.value as f64]);
+
+                    if let Some(max_points) = max_points {
+                        while series.len() > max_points {
+                            series.pop_front();
+                        }
+                    }
+                    if let Some(max_age) = max_age {
+                        while series
+                            .front()
+                            .is_some_and(|p| f.timestamp as f64 - p[0] > max_age as f64)
+                        {
+                            series.pop_front();
+                        }
+                    }
+                }
                 Err(e) => break e,
             }
         };
@@ -106,6 +240,18 @@ impl Reader {
                 self.status = s;
                 ret
             }
+            Ok(ReaderStatus::ParamValue {
+                board_id,
+                param_id,
+                value,
+            }) => {
+                self.params.insert((board_id, param_id), value);
+                ret
+            }
+            Ok(s @ ReaderStatus::CmdTimeout { .. }) => {
+                self.status = s;
+                ret
+            }
             Err(TryRecvError::Disconnected) => {
                 self.status =
                     ReaderStatus::Stopped(Some("Reader Disconnected unexpectedly".into()));
@@ -116,9 +262,19 @@ impl Reader {
         }
     }
 
-    pub fn start_reading(&mut self, path: &str, baud: u32) {
+    pub fn start_reading(&mut self, endpoint: Endpoint, mode: FrameMode) {
         if self.comm.is_none() {
-            match Self::spawn_reader(path, baud) {
+            match Self::spawn_reader(endpoint, mode) {
+                Ok(r) => self.comm = Some(r),
+                Err(e) => self.status = ReaderStatus::Stopped(Some(e.to_string())),
+            }
+        }
+    }
+
+    /// replay a CSV log previously written by `start_logging`, at `speed` times the original pace
+    pub fn start_replay(&mut self, path: Box<Path>, speed: f32) {
+        if self.comm.is_none() {
+            match Self::spawn_replay(path, speed) {
                 Ok(r) => self.comm = Some(r),
                 Err(e) => self.status = ReaderStatus::Stopped(Some(e.to_string())),
             }
@@ -154,6 +310,26 @@ impl Reader {
         matches!(self.status, ReaderStatus::Logging)
     }
 
+    /// request the current value of `param_id` on `board_id`; result lands in `Reader::params`
+    pub fn read_parameter(&mut self, board_id: u8, param_id: u8) {
+        let Some(ref mut r) = self.comm else {
+            return;
+        };
+        let _ = r.command_tx.send(Commands::ReadParam { board_id, param_id });
+    }
+
+    /// write `value` to `param_id` on `board_id`; the device's echo lands in `Reader::params`
+    pub fn write_parameter(&mut self, board_id: u8, param_id: u8, value: i16) {
+        let Some(ref mut r) = self.comm else {
+            return;
+        };
+        let _ = r.command_tx.send(Commands::WriteParam {
+            board_id,
+            param_id,
+            value,
+        });
+    }
+
     pub fn reader_status(&self) -> String {
         match self.status {
             ReaderStatus::LogErr(ref e) => e.to_owned(),
@@ -161,19 +337,25 @@ impl Reader {
             ReaderStatus::Logging => "Logging".to_owned(),
             ReaderStatus::Stopped(Some(ref reason)) => format!("Stopped ({reason})"),
             ReaderStatus::Stopped(_) => "Stopped".to_owned(),
+            ReaderStatus::ParamValue {
+                board_id,
+                param_id,
+                value,
+            } => format!("Param {param_id}@{board_id} = {value}"),
+            ReaderStatus::CmdTimeout { board_id, param_id } => {
+                format!("Command timeout (board {board_id}, param {param_id})")
+            }
         }
     }
 
-    fn spawn_reader(path: &str, baud: u32) -> Result<ReaderComm, io::Error> {
-        let port = serialport::new(path, baud)
-            .timeout(Duration::from_millis(100))
-            .open()?;
+    fn spawn_reader(endpoint: Endpoint, mode: FrameMode) -> Result<ReaderComm, io::Error> {
+        let port = Source::open(&endpoint)?;
 
         let (frame_tx, frame_rx) = mpsc::channel();
         let (command_tx, command_rx) = mpsc::channel();
         let (status_tx, status_rx) = mpsc::channel();
 
-        thread::spawn(|| Self::reader_main(port, frame_tx, status_tx, command_rx));
+        thread::spawn(move || Self::reader_main(port, mode, frame_tx, status_tx, command_rx));
         Ok(ReaderComm {
             command_tx,
             frame_rx,
@@ -181,18 +363,21 @@ impl Reader {
         })
     }
 
-    /// reader main function. Reads frames from the serial port and sends them into `frame_tx`
+    /// reader main function. Reads frames from `port` and sends them into `frame_tx`
     fn reader_main(
-        port: Box<dyn SerialPort>,
+        port: Source,
+        mode: FrameMode,
         frame_tx: mpsc::Sender<Frame>,
         status_tx: mpsc::Sender<ReaderStatus>,
         command_rx: mpsc::Receiver<Commands>,
     ) {
         let reader = BufReader::new(port);
-        let mut reader = FrameReader::new(reader);
+        let mut reader = FrameReader::new(reader, mode);
         let mut err_retry = 0u8;
         let mut logger: Option<Writer<File>> = None;
         let start = Instant::now();
+        // board_id, param_id, sent_at of the in-flight parameter request, if any
+        let mut pending: Option<(u8, u8, Instant)> = None;
         status_tx
             .send(ReaderStatus::Running)
             .expect("Main Thread dropped status reciver");
@@ -246,14 +431,35 @@ impl Reader {
                         .send(ReaderStatus::Logging)
                         .expect("Main Thread dropped status reciver");
                 }
+                Ok(Commands::ReadParam { board_id, param_id }) => {
+                    let _ = reader.send_request(board_id, param_id, None);
+                    pending = Some((board_id, param_id, Instant::now()));
+                }
+                Ok(Commands::WriteParam {
+                    board_id,
+                    param_id,
+                    value,
+                }) => {
+                    let _ = reader.send_request(board_id, param_id, Some(value));
+                    pending = Some((board_id, param_id, Instant::now()));
+                }
                 Err(mpsc::TryRecvError::Empty) => (),
                 Err(mpsc::TryRecvError::Disconnected) => {
                     panic!("Main Thread dropped command sender")
                 }
             }
 
+            if let Some((board_id, param_id, sent_at)) = pending {
+                if sent_at.elapsed() > Duration::from_millis(500) {
+                    pending = None;
+                    status_tx
+                        .send(ReaderStatus::CmdTimeout { board_id, param_id })
+                        .expect("Main Thread dropped status reciver");
+                }
+            }
+
             match reader.next_frame() {
-                Ok(f) => {
+                Ok(Inbound::Data(f)) => {
                     err_retry = 0;
                     if let Some(ref mut wtr) = logger {
                         let now = start.elapsed().as_millis();
@@ -273,6 +479,27 @@ impl Reader {
                     }
                     frame_tx.send(f).expect("Main Thread dropped frame reciver");
                 }
+                Ok(Inbound::Param {
+                    board_id,
+                    param_id,
+                    value,
+                }) => {
+                    err_retry = 0;
+                    if pending.is_some_and(|(b, p, _)| b == board_id && p == param_id) {
+                        pending = None;
+                        status_tx
+                            .send(ReaderStatus::ParamValue {
+                                board_id,
+                                param_id,
+                                value,
+                            })
+                            .expect("Main Thread dropped status reciver");
+                    }
+                }
+                // a read timing out just means the source was idle for `Source::READ_TIMEOUT`;
+                // that's expected on a quiet network source and must not count towards err_retry
+                Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                }
                 Err(e) => {
                     err_retry += 1;
                     error!("{e:?}");
@@ -286,25 +513,212 @@ impl Reader {
             };
         }
     }
+
+    fn spawn_replay(path: Box<Path>, speed: f32) -> Result<ReaderComm, io::Error> {
+        let rdr = csv::Reader::from_path(path)?;
+
+        let (frame_tx, frame_rx) = mpsc::channel();
+        let (command_tx, command_rx) = mpsc::channel();
+        let (status_tx, status_rx) = mpsc::channel();
+
+        thread::spawn(move || Self::replay_main(rdr, speed, frame_tx, status_tx, command_rx));
+        Ok(ReaderComm {
+            command_tx,
+            frame_rx,
+            status_rx,
+        })
+    }
+
+    /// replay main function. Reads `start_logging`'s CSV columns and feeds them into `frame_tx`,
+    /// sleeping between records according to the `Read Time` column and `speed`
+    fn replay_main(
+        mut rdr: csv::Reader<File>,
+        speed: f32,
+        frame_tx: mpsc::Sender<Frame>,
+        status_tx: mpsc::Sender<ReaderStatus>,
+        command_rx: mpsc::Receiver<Commands>,
+    ) {
+        status_tx
+            .send(ReaderStatus::Running)
+            .expect("Main Thread dropped status reciver");
+
+        let mut last_read_time: Option<u64> = None;
+
+        for record in rdr.records() {
+            match command_rx.try_recv() {
+                Ok(Commands::Stop) => {
+                    status_tx
+                        .send(ReaderStatus::Stopped(None))
+                        .expect("Main Thread dropped status reciver");
+                    return;
+                }
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    panic!("Main Thread dropped command sender")
+                }
+                _ => (),
+            }
+
+            let record = match record {
+                Ok(r) => r,
+                Err(e) => {
+                    status_tx
+                        .send(ReaderStatus::Stopped(Some(e.to_string())))
+                        .expect("Main Thread dropped status reciver");
+                    return;
+                }
+            };
+
+            let Ok(sensor_id) = record[0].parse() else {
+                continue;
+            };
+            let Ok(board_id) = record[1].parse() else {
+                continue;
+            };
+            let Ok(read_time): Result<u64, _> = record[2].parse() else {
+                continue;
+            };
+            let Ok(timestamp) = record[3].parse() else {
+                continue;
+            };
+            let Ok(value) = record[4].parse() else {
+                continue;
+            };
+
+            if let Some(last) = last_read_time {
+                let delay = Duration::from_millis(read_time.saturating_sub(last));
+                thread::sleep(delay.div_f32(speed.max(0.01)));
+            }
+            last_read_time = Some(read_time);
+
+            frame_tx
+                .send(Frame {
+                    board_id,
+                    sensor_id,
+                    value,
+                    timestamp,
+                })
+                .expect("Main Thread dropped frame reciver");
+        }
+
+        status_tx
+            .send(ReaderStatus::Stopped(None))
+            .expect("Main Thread dropped status reciver");
+    }
 }
 
 impl FrameReader {
-    fn new(port: BufReader<Box<dyn SerialPort>>) -> Self {
+    fn new(port: BufReader<Source>, mode: FrameMode) -> Self {
         Self {
             port,
-            buf: String::with_capacity(64),
+            mode,
+            ascii_buf: String::with_capacity(64),
+            cobs_buf: Vec::with_capacity(64),
+        }
+    }
+
+    fn next_frame(&mut self) -> io::Result<Inbound> {
+        match self.mode {
+            FrameMode::Ascii => self.next_frame_ascii(),
+            FrameMode::Cobs => self.next_frame_cobs(),
         }
     }
 
-    fn next_frame(&mut self) -> io::Result<Frame> {
-        self.port.read_line(&mut self.buf)?;
-        let res = self.buf.as_str().try_into();
-        self.buf.clear();
+    fn next_frame_ascii(&mut self) -> io::Result<Inbound> {
+        self.port.read_line(&mut self.ascii_buf)?;
+        let res = self.ascii_buf.as_str().try_into();
+        self.ascii_buf.clear();
         res
     }
+
+    fn next_frame_cobs(&mut self) -> io::Result<Inbound> {
+        self.cobs_buf.clear();
+        self.port.read_until(0x00, &mut self.cobs_buf)?;
+        if self.cobs_buf.last() == Some(&0x00) {
+            self.cobs_buf.pop();
+        }
+        let payload = cobs_decode(&self.cobs_buf)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed COBS packet"))?;
+        payload.as_slice().try_into()
+    }
+
+    /// send a parameter request (a read if `value` is `None`, a write otherwise) to the device
+    fn send_request(&mut self, board_id: u8, param_id: u8, value: Option<i16>) -> io::Result<()> {
+        match self.mode {
+            FrameMode::Ascii => {
+                let op = if value.is_some() { 'W' } else { 'R' };
+                let payload = format!("\rP {op} {board_id} {param_id} {}\n", value.unwrap_or(0));
+                self.port.get_mut().write_all(payload.as_bytes())
+            }
+            FrameMode::Cobs => {
+                let mut raw = vec![board_id, param_id];
+                raw.insert(0, if value.is_some() { TAG_WRITE_REQ } else { TAG_READ_REQ });
+                if let Some(value) = value {
+                    raw.extend_from_slice(&value.to_le_bytes());
+                }
+                let mut encoded = cobs_encode(&raw);
+                encoded.push(0x00);
+                self.port.get_mut().write_all(&encoded)
+            }
+        }
+    }
+}
+
+const TAG_DATA: u8 = 0x01;
+const TAG_PARAM: u8 = 0x02;
+const TAG_READ_REQ: u8 = 0x10;
+const TAG_WRITE_REQ: u8 = 0x11;
+
+/// decode a single COBS-framed packet (without its trailing `0x00` delimiter)
+fn cobs_decode(encoded: &[u8]) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let code = encoded[i] as usize;
+        if code == 0 {
+            return None;
+        }
+        i += 1;
+        let end = i + code - 1;
+        if end > encoded.len() {
+            return None;
+        }
+        out.extend_from_slice(&encoded[i..end]);
+        i = end;
+        if code < 0xFF && i < encoded.len() {
+            out.push(0x00);
+        }
+    }
+    Some(out)
 }
 
-impl TryFrom<&str> for Frame {
+/// encode a packet (without the trailing `0x00` delimiter, which the caller appends)
+fn cobs_encode(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + data.len() / 254 + 1);
+    let mut code_idx = 0;
+    out.push(0);
+    let mut code = 1u8;
+    for &byte in data {
+        if byte == 0x00 {
+            out[code_idx] = code;
+            code_idx = out.len();
+            out.push(0);
+            code = 1;
+        } else {
+            out.push(byte);
+            code += 1;
+            if code == 0xFF {
+                out[code_idx] = code;
+                code_idx = out.len();
+                out.push(0);
+                code = 1;
+            }
+        }
+    }
+    out[code_idx] = code;
+    out
+}
+
+impl TryFrom<&str> for Inbound {
     type Error = io::Error;
 
     fn try_from(slice: &str) -> Result<Self, Self::Error> {
@@ -315,20 +729,71 @@ impl TryFrom<&str> for Frame {
             .split(" ")
             .filter(|s| !s.is_empty())
             .collect();
-        
+
+        if values.first() == Some(&"P") {
+            if values.len() != 4 {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, slice.to_owned()));
+            }
+            let invalid = || io::Error::new(io::ErrorKind::InvalidData, slice.to_owned());
+            let board_id: u8 = values[1].parse().map_err(|_| invalid())?;
+            let param_id: u8 = values[2].parse().map_err(|_| invalid())?;
+            let value: i16 = values[3].parse().map_err(|_| invalid())?;
+            return Ok(Inbound::Param {
+                board_id,
+                param_id,
+                value,
+            });
+        }
+
         if values.len() != 4 {
             return Err(io::Error::new(io::ErrorKind::InvalidData, slice.to_owned()));
         };
 
-        let sensor_id: u8 = values[0].parse().unwrap();
-        let board_id: u8 = values[1].parse().unwrap();
-        let value: i16 = values[2].parse().unwrap();
-        let timestamp: u32 = values[3].parse().unwrap();
-        Ok(Frame {
+        let invalid = || io::Error::new(io::ErrorKind::InvalidData, slice.to_owned());
+        let sensor_id: u8 = values[0].parse().map_err(|_| invalid())?;
+        let board_id: u8 = values[1].parse().map_err(|_| invalid())?;
+        let value: i16 = values[2].parse().map_err(|_| invalid())?;
+        let timestamp: u32 = values[3].parse().map_err(|_| invalid())?;
+        Ok(Inbound::Data(Frame {
             board_id,
             sensor_id,
             value,
             timestamp,
-        })
+        }))
+    }
+}
+
+impl TryFrom<&[u8]> for Inbound {
+    type Error = io::Error;
+
+    /// tag byte followed by a fixed little-endian payload, see `TAG_DATA`/`TAG_PARAM`
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let (&tag, rest) = bytes
+            .split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "empty frame payload"))?;
+        match tag {
+            TAG_DATA => {
+                let rest: [u8; 8] = rest.try_into().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "unexpected frame payload length")
+                })?;
+                Ok(Inbound::Data(Frame {
+                    board_id: rest[0],
+                    sensor_id: rest[1],
+                    value: i16::from_le_bytes([rest[2], rest[3]]),
+                    timestamp: u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]),
+                }))
+            }
+            TAG_PARAM => {
+                let rest: [u8; 4] = rest.try_into().map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "unexpected param payload length")
+                })?;
+                Ok(Inbound::Param {
+                    board_id: rest[0],
+                    param_id: rest[1],
+                    value: i16::from_le_bytes([rest[2], rest[3]]),
+                })
+            }
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "unknown frame tag")),
+        }
     }
 }
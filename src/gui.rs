@@ -1,26 +1,70 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 
 use eframe::egui::{self, Ui, Widget};
 
 use egui_plot::{Legend, Line, Plot, PlotPoints};
 
-use crate::data_reader::Reader;
+use crate::data_reader::{Endpoint, FrameMode, Reader};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SourceKind {
+    Serial,
+    Tcp,
+    Udp,
+}
 
 pub struct MyApp {
+    source_kind: SourceKind,
     path: String,
     baud: u32,
+    address: String,
+    frame_mode: FrameMode,
+
+    param_board: u8,
+    param_id: u8,
+    param_value: i16,
+
+    replay_path: String,
+    replay_speed: f32,
+
+    retain_points: bool,
+    max_points: usize,
+    retain_age: bool,
+    max_age: u32,
 
     labels: HashSet<String>,
     reader: Reader,
+
+    /// downsampled plot points per series, keyed on `(newest timestamp, threshold)` so it is only
+    /// recomputed when a frame with a new timestamp arrives or the target point count changes;
+    /// the bit pattern of the newest sample's timestamp is used instead of the series length
+    /// because retention eviction keeps the length constant once a series is at capacity
+    downsample_cache: HashMap<(u8, u8), (u64, usize, Vec<[f64; 2]>)>,
 }
 
 impl MyApp {
     pub fn new(_cc: &eframe::CreationContext) -> Self {
         Self {
+            source_kind: SourceKind::Serial,
             path: "/dev/ttyUSB0".to_owned(),
             baud: 38400,
+            address: "127.0.0.1:9000".to_owned(),
+            frame_mode: FrameMode::default(),
+            param_board: 0,
+            param_id: 0,
+            param_value: 0,
+            replay_path: String::new(),
+            replay_speed: 1.0,
+            retain_points: false,
+            max_points: 10_000,
+            retain_age: false,
+            max_age: 60_000,
             reader: Default::default(),
             labels: Default::default(),
+            downsample_cache: Default::default(),
         }
     }
 
@@ -28,14 +72,40 @@ impl MyApp {
         ui.heading("Data Viz");
         egui::Grid::new("control_area").show(ui, |ui| {
             let size = [100.0, 0.0].into();
-            ui.label("Port:");
-            egui::TextEdit::singleline(&mut self.path)
-                .min_size(size)
-                .show(ui);
+            ui.label("Source:");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.source_kind, SourceKind::Serial, "Serial");
+                ui.radio_value(&mut self.source_kind, SourceKind::Tcp, "TCP");
+                ui.radio_value(&mut self.source_kind, SourceKind::Udp, "UDP");
+            });
             ui.end_row();
 
-            ui.label("Baudrate:");
-            egui::Slider::new(&mut self.baud, 9600..=921_600).ui(ui);
+            match self.source_kind {
+                SourceKind::Serial => {
+                    ui.label("Port:");
+                    egui::TextEdit::singleline(&mut self.path)
+                        .min_size(size)
+                        .show(ui);
+                    ui.end_row();
+
+                    ui.label("Baudrate:");
+                    egui::Slider::new(&mut self.baud, 9600..=921_600).ui(ui);
+                    ui.end_row();
+                }
+                SourceKind::Tcp | SourceKind::Udp => {
+                    ui.label("Address:");
+                    egui::TextEdit::singleline(&mut self.address)
+                        .min_size(size)
+                        .show(ui);
+                    ui.end_row();
+                }
+            }
+
+            ui.label("Frame format:");
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.frame_mode, FrameMode::Ascii, "ASCII");
+                ui.radio_value(&mut self.frame_mode, FrameMode::Cobs, "COBS");
+            });
             ui.end_row();
 
             ui.label("");
@@ -52,7 +122,15 @@ impl MyApp {
                 .ui(ui)
                 .clicked()
             {
-                self.reader.start_reading(&self.path, self.baud)
+                let endpoint = match self.source_kind {
+                    SourceKind::Serial => Endpoint::Serial {
+                        path: self.path.clone(),
+                        baud: self.baud,
+                    },
+                    SourceKind::Tcp => Endpoint::Tcp(self.address.clone()),
+                    SourceKind::Udp => Endpoint::Udp(self.address.clone()),
+                };
+                self.reader.start_reading(endpoint, self.frame_mode)
             }
             ui.end_row();
 
@@ -67,6 +145,86 @@ impl MyApp {
             ui.end_row();
         });
 
+        ui.separator();
+        ui.label("Parameter:");
+        egui::Grid::new("param_area").show(ui, |ui| {
+            ui.label("Board:");
+            egui::DragValue::new(&mut self.param_board).ui(ui);
+            ui.end_row();
+
+            ui.label("Param:");
+            egui::DragValue::new(&mut self.param_id).ui(ui);
+            ui.end_row();
+
+            ui.label("Value:");
+            egui::DragValue::new(&mut self.param_value).ui(ui);
+            ui.end_row();
+
+            ui.label("");
+            ui.horizontal(|ui| {
+                if ui.button("Read").clicked() {
+                    self.reader.read_parameter(self.param_board, self.param_id);
+                }
+                if ui.button("Write").clicked() {
+                    self.reader.write_parameter(
+                        self.param_board,
+                        self.param_id,
+                        self.param_value,
+                    );
+                }
+            });
+            ui.end_row();
+        });
+        if let Some(value) = self
+            .reader
+            .params
+            .get(&(self.param_board, self.param_id))
+        {
+            ui.label(format!("Last value: {value}"));
+        }
+
+        ui.separator();
+        ui.label("Replay:");
+        egui::Grid::new("replay_area").show(ui, |ui| {
+            let size = [100.0, 0.0].into();
+            ui.label("Log path:");
+            egui::TextEdit::singleline(&mut self.replay_path)
+                .min_size(size)
+                .show(ui);
+            ui.end_row();
+
+            ui.label("Speed:");
+            egui::Slider::new(&mut self.replay_speed, 0.1..=10.0)
+                .suffix("x")
+                .ui(ui);
+            ui.end_row();
+
+            ui.label("");
+            if egui::Button::new("Open log")
+                .min_size(size)
+                .ui(ui)
+                .clicked()
+            {
+                self.reader
+                    .start_replay(Path::new(&self.replay_path).into(), self.replay_speed);
+            }
+            ui.end_row();
+        });
+
+        ui.separator();
+        ui.label("Retention:");
+        egui::Grid::new("retention_area").show(ui, |ui| {
+            ui.checkbox(&mut self.retain_points, "Max points");
+            egui::DragValue::new(&mut self.max_points).ui(ui);
+            ui.end_row();
+
+            ui.checkbox(&mut self.retain_age, "Max age");
+            egui::DragValue::new(&mut self.max_age).ui(ui);
+            ui.end_row();
+        });
+        self.reader.max_points = self.retain_points.then_some(self.max_points);
+        self.reader.max_age = self.retain_age.then_some(self.max_age);
+
         ui.separator();
         ui.label("Datenreihen:");
         for label in self.reader.data.keys() {
@@ -81,20 +239,88 @@ impl MyApp {
     }
 
     fn show_plot(&mut self, ui: &mut Ui) {
+        // one point per horizontal pixel is enough fidelity for the plot to look identical
+        let threshold = (ui.available_width() as usize).max(3);
+
+        {
+            let data = &mut self.reader.data;
+            let labels = &self.labels;
+            let cache = &mut self.downsample_cache;
+            for (key, points) in data.iter_mut().filter(|(l, _)| labels.contains(*l)) {
+                let points = points.make_contiguous();
+                let newest = points.last().map(|p| p[0].to_bits()).unwrap_or(0);
+                let up_to_date =
+                    matches!(cache.get(key), Some((n, t, _)) if *n == newest && *t == threshold);
+                if !up_to_date {
+                    cache.insert(*key, (newest, threshold, lttb(points, threshold)));
+                }
+            }
+        }
+
         let plot = Plot::new("sensor_plt").legend(Legend::default());
         plot.show(ui, |plt_ui| {
-            for (label, data) in self
+            for (key, _) in self
                 .reader
                 .data
                 .iter()
                 .filter(|(l, _)| self.labels.contains(*l))
             {
-                plt_ui.line(Line::new(PlotPoints::from(data.clone())).name(label.to_string()));
+                if let Some((_, _, points)) = self.downsample_cache.get(key) {
+                    plt_ui.line(Line::new(PlotPoints::from(points.clone())).name(format!("{key:?}")));
+                }
             }
         });
     }
 }
 
+/// Largest-Triangle-Three-Buckets downsampling: always keeps the first and last point, then
+/// for each of `threshold - 2` buckets picks the point forming the largest-area triangle with
+/// the previously selected point and the average of the next bucket
+fn lttb(data: &[[f64; 2]], threshold: usize) -> Vec<[f64; 2]> {
+    if threshold >= data.len() || threshold < 3 {
+        return data.to_vec();
+    }
+
+    let mut sampled = Vec::with_capacity(threshold);
+    sampled.push(data[0]);
+
+    let bucket_size = (data.len() - 2) as f64 / (threshold - 2) as f64;
+    let mut prev = 0usize;
+
+    for i in 0..threshold - 2 {
+        let bucket_start = (i as f64 * bucket_size) as usize + 1;
+        let bucket_end = (((i + 1) as f64 * bucket_size) as usize + 1).min(data.len() - 1);
+
+        let next_start = bucket_end;
+        let next_end = (((i + 2) as f64 * bucket_size) as usize + 1).min(data.len());
+        let next_bucket = &data[next_start..next_end.max(next_start + 1).min(data.len())];
+        let (avg_x, avg_y) = next_bucket
+            .iter()
+            .fold((0.0, 0.0), |(sx, sy), p| (sx + p[0], sy + p[1]));
+        let len = next_bucket.len().max(1) as f64;
+        let (avg_x, avg_y) = (avg_x / len, avg_y / len);
+
+        let (ax, ay) = (data[prev][0], data[prev][1]);
+
+        let mut max_area = -1.0;
+        let mut max_idx = bucket_start;
+        for idx in bucket_start..bucket_end.max(bucket_start + 1) {
+            let (bx, by) = (data[idx][0], data[idx][1]);
+            let area = 0.5 * ((ax - avg_x) * (by - ay) - (ax - bx) * (avg_y - ay)).abs();
+            if area > max_area {
+                max_area = area;
+                max_idx = idx;
+            }
+        }
+
+        sampled.push(data[max_idx]);
+        prev = max_idx;
+    }
+
+    sampled.push(data[data.len() - 1]);
+    sampled
+}
+
 impl eframe::App for MyApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         if let Some(d) = self.reader.process() {